@@ -1,16 +1,447 @@
 use eframe::egui;
-use image::{GenericImageView, RgbaImage};
-use std::{fs::File, io::{self, Write, Read}};
+use image::{AnimationDecoder, GenericImageView, RgbaImage};
+use image::codecs::gif::GifDecoder;
+use std::{fs::File, io::{self, BufReader, Write, Read}};
+use std::time::Duration;
 use flate2::{write::ZlibEncoder, Compression};
 use std::path::{Path, PathBuf};
 use rfd::FileDialog;
 
+/// Legacy magic: raw (unfiltered) zlib-compressed RGBA.
+const MAGIC_LEGACY: &[u8; 4] = b"MYIF";
+/// Single-frame magic: per-scanline PNG-style prefiltering applied before zlib.
+const MAGIC_FILTERED: &[u8; 4] = b"MYI1";
+/// Animated magic: one or more prefiltered RGBA frames with per-frame delay
+/// times. Still read, but new files are written as [`MAGIC_ANIM_CT`].
+const MAGIC_ANIM: &[u8; 4] = b"MYI2";
+/// Palette magic: a median-cut palette followed by zlib-compressed indices.
+const MAGIC_PALETTE: &[u8; 4] = b"MYI3";
+/// Channel-typed animation magic: like [`MAGIC_ANIM`] but with a per-file color
+/// type byte so opaque/grayscale images store fewer bytes per pixel.
+const MAGIC_ANIM_CT: &[u8; 4] = b"MYI4";
+
+/// Maximum number of palette entries produced by the quantizer.
+const MAX_PALETTE: usize = 256;
+
+/// A single decoded animation frame and the time it is displayed for.
+struct ShivanoshFrame {
+    image: RgbaImage,
+    delay_ms: u16,
+}
+
+/// Maximum edge length of a lazily-built gallery thumbnail, in pixels.
+const THUMB_MAX: u32 = 128;
+
+/// One entry in the thumbnail gallery: a source path, its decode outcome and a
+/// lazily-built texture that is evicted while the entry is scrolled off-screen.
+struct GalleryItem {
+    path: PathBuf,
+    /// Downscaled preview, or `None` if the file could not be decoded.
+    thumbnail: Option<RgbaImage>,
+    /// Per-file status row shown beneath the thumbnail.
+    status: String,
+    texture: Option<eframe::egui::TextureHandle>,
+}
+
+/// The minimal channel layout needed to store an image without loss, mirroring
+/// the color-type reduction done by lossless PNG optimizers.
+#[derive(Clone, Copy, PartialEq)]
+enum ColorType {
+    Gray,
+    GrayAlpha,
+    Rgb,
+    Rgba,
+}
+
+impl ColorType {
+    fn to_byte(self) -> u8 {
+        match self {
+            ColorType::Gray => 0,
+            ColorType::GrayAlpha => 1,
+            ColorType::Rgb => 2,
+            ColorType::Rgba => 3,
+        }
+    }
+
+    fn from_byte(b: u8) -> io::Result<Self> {
+        match b {
+            0 => Ok(ColorType::Gray),
+            1 => Ok(ColorType::GrayAlpha),
+            2 => Ok(ColorType::Rgb),
+            3 => Ok(ColorType::Rgba),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidData, "unknown color type")),
+        }
+    }
+
+    fn bytes_per_pixel(self) -> usize {
+        match self {
+            ColorType::Gray => 1,
+            ColorType::GrayAlpha => 2,
+            ColorType::Rgb => 3,
+            ColorType::Rgba => 4,
+        }
+    }
+}
+
+/// Detect the smallest [`ColorType`] that can represent every frame without
+/// loss: drop alpha when fully opaque, collapse to gray when R==G==B.
+fn detect_color_type(frames: &[ShivanoshFrame]) -> ColorType {
+    let mut has_alpha = false;
+    let mut has_color = false;
+    for frame in frames {
+        for pixel in frame.image.pixels() {
+            let [r, g, b, a] = pixel.0;
+            if a != 255 {
+                has_alpha = true;
+            }
+            if r != g || g != b {
+                has_color = true;
+            }
+        }
+    }
+    match (has_color, has_alpha) {
+        (true, true) => ColorType::Rgba,
+        (true, false) => ColorType::Rgb,
+        (false, true) => ColorType::GrayAlpha,
+        (false, false) => ColorType::Gray,
+    }
+}
+
+/// Pack an image's pixels into the byte layout of `ct`.
+fn pack_pixels(image: &RgbaImage, ct: ColorType) -> Vec<u8> {
+    let mut out = Vec::with_capacity(image.as_raw().len());
+    for pixel in image.pixels() {
+        let [r, g, b, a] = pixel.0;
+        match ct {
+            ColorType::Gray => out.push(r),
+            ColorType::GrayAlpha => out.extend_from_slice(&[r, a]),
+            ColorType::Rgb => out.extend_from_slice(&[r, g, b]),
+            ColorType::Rgba => out.extend_from_slice(&[r, g, b, a]),
+        }
+    }
+    out
+}
+
+/// Expand a packed buffer back into a full `RgbaImage`, broadcasting gray to RGB
+/// and filling alpha with 255 where it was omitted.
+fn unpack_pixels(data: &[u8], width: u32, height: u32, ct: ColorType) -> io::Result<RgbaImage> {
+    let bpp = ct.bytes_per_pixel();
+    let expected = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|n| n.checked_mul(bpp));
+    if expected != Some(data.len()) {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "frame size mismatch"));
+    }
+    let mut image = RgbaImage::new(width, height);
+    for (i, chunk) in data.chunks_exact(bpp).enumerate() {
+        let rgba = match ct {
+            ColorType::Gray => [chunk[0], chunk[0], chunk[0], 255],
+            ColorType::GrayAlpha => [chunk[0], chunk[0], chunk[0], chunk[1]],
+            ColorType::Rgb => [chunk[0], chunk[1], chunk[2], 255],
+            ColorType::Rgba => [chunk[0], chunk[1], chunk[2], chunk[3]],
+        };
+        image.put_pixel(i as u32 % width, i as u32 / width, image::Rgba(rgba));
+    }
+    Ok(image)
+}
+
+/// Standard image formats a decoded `.shivanosh` file can be exported back to.
+#[derive(Clone, Copy)]
+enum ExportFormat {
+    Png,
+    Jpeg,
+    /// Listed in the spec, but the pinned `image` crate ships no WebP encoder,
+    /// so the dispatch returns an explicit unsupported-target error for it.
+    WebP,
+    Bmp,
+    Tiff,
+}
+
+impl ExportFormat {
+    /// Every export target offered in the save dialog, in the order shown there.
+    /// WebP is offered, but exporting to it returns an unsupported-target error.
+    const ALL: [ExportFormat; 5] = [
+        ExportFormat::Png,
+        ExportFormat::Jpeg,
+        ExportFormat::WebP,
+        ExportFormat::Bmp,
+        ExportFormat::Tiff,
+    ];
+
+    /// The canonical file extension for this format (no leading dot).
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Png => "png",
+            ExportFormat::Jpeg => "jpg",
+            ExportFormat::WebP => "webp",
+            ExportFormat::Bmp => "bmp",
+            ExportFormat::Tiff => "tiff",
+        }
+    }
+
+    /// Human-readable label for the dialog filter.
+    fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Png => "PNG image",
+            ExportFormat::Jpeg => "JPEG image",
+            ExportFormat::WebP => "WebP image",
+            ExportFormat::Bmp => "BMP image",
+            ExportFormat::Tiff => "TIFF image",
+        }
+    }
+}
+
+/// Pack one frame to its color type, prefilter the scanlines and zlib-compress
+/// the result into a stored block.
+fn encode_frame_block(img: &RgbaImage, ct: ColorType) -> io::Result<Vec<u8>> {
+    let packed = pack_pixels(img, ct);
+    let filtered = filter_scanlines(&packed, img.width(), ct.bytes_per_pixel());
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(&filtered)?;
+    encoder.finish()
+}
+
+/// Decompress, (optionally) unfilter and unpack one stored block back into a
+/// full `RgbaImage`.
+fn decode_frame_block(
+    block: &[u8],
+    width: u32,
+    height: u32,
+    ct: ColorType,
+    filtered: bool,
+) -> io::Result<RgbaImage> {
+    let mut decoder = flate2::read::ZlibDecoder::new(block);
+    let mut data = Vec::new();
+    decoder.read_to_end(&mut data)?;
+    if filtered {
+        data = unfilter_scanlines(&data, width, height, ct.bytes_per_pixel())?;
+    }
+    unpack_pixels(&data, width, height, ct)
+}
+
+/// Paeth predictor, as defined by the PNG specification: returns whichever of
+/// the left (`a`), above (`b`) or upper-left (`c`) neighbour is closest to the
+/// initial estimate `a + b - c`.
+fn paeth_predictor(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i32 + b as i32 - c as i32;
+    let pa = (p - a as i32).abs();
+    let pb = (p - b as i32).abs();
+    let pc = (p - c as i32).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Sum of the absolute values of a filtered row, treating each byte as a signed
+/// residual. This is the minimum-sum-of-absolute-differences heuristic used by
+/// optimizers like oxipng to pick the cheapest filter per scanline.
+fn filtered_row_cost(row: &[u8]) -> u64 {
+    row.iter().map(|&b| (b as i8).unsigned_abs() as u64).sum()
+}
+
+/// Apply the best of the five PNG delta filters to each scanline of a packed
+/// pixel buffer, prepending a 1-byte filter tag to every stored row. Left/above
+/// neighbours one pixel (`bpp` bytes) back and one row up are used; out-of-bounds
+/// neighbours are treated as 0.
+fn filter_scanlines(raw: &[u8], width: u32, bpp: usize) -> Vec<u8> {
+    let stride = width as usize * bpp;
+    if stride == 0 {
+        return Vec::new();
+    }
+    let mut out = Vec::with_capacity(raw.len() + raw.len() / stride);
+    let mut candidate = vec![0u8; stride];
+    for (row_idx, row) in raw.chunks_exact(stride).enumerate() {
+        let above: &[u8] = if row_idx == 0 {
+            &[]
+        } else {
+            &raw[(row_idx - 1) * stride..row_idx * stride]
+        };
+
+        let mut best_tag = 0u8;
+        let mut best_row = row.to_vec();
+        let mut best_cost = filtered_row_cost(row);
+
+        for tag in 1u8..=4 {
+            for i in 0..stride {
+                let left = if i >= bpp { row[i - bpp] } else { 0 };
+                let up = above.get(i).copied().unwrap_or(0);
+                let upper_left = if i >= bpp { above.get(i - bpp).copied().unwrap_or(0) } else { 0 };
+                let pred = match tag {
+                    1 => left,
+                    2 => up,
+                    3 => ((left as u16 + up as u16) / 2) as u8,
+                    4 => paeth_predictor(left, up, upper_left),
+                    _ => unreachable!(),
+                };
+                candidate[i] = row[i].wrapping_sub(pred);
+            }
+            let cost = filtered_row_cost(&candidate);
+            if cost < best_cost {
+                best_cost = cost;
+                best_tag = tag;
+                best_row.copy_from_slice(&candidate);
+            }
+        }
+
+        out.push(best_tag);
+        out.extend_from_slice(&best_row);
+    }
+    out
+}
+
+/// Reverse [`filter_scanlines`], reconstructing the packed pixel buffer by
+/// adding the left/above predictors back in the same order they were subtracted.
+fn unfilter_scanlines(data: &[u8], width: u32, height: u32, bpp: usize) -> io::Result<Vec<u8>> {
+    let stride = width as usize * bpp;
+    // Check the sizes from the header against the data before allocating, so a
+    // corrupt header yields an error instead of an overflow or a huge buffer.
+    let needed = (stride + 1).checked_mul(height as usize);
+    if stride != 0 && needed.map_or(true, |n| n > data.len()) {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "truncated filtered scanline"));
+    }
+    let mut raw = vec![0u8; stride * height as usize];
+    if stride == 0 {
+        return Ok(raw);
+    }
+    let mut pos = 0usize;
+    for row_idx in 0..height as usize {
+        let tag = *data.get(pos).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, "truncated filtered scanline")
+        })?;
+        pos += 1;
+        let src = data.get(pos..pos + stride).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, "truncated filtered scanline")
+        })?;
+        pos += stride;
+        for i in 0..stride {
+            let left = if i >= bpp { raw[row_idx * stride + i - bpp] } else { 0 };
+            let up = if row_idx == 0 { 0 } else { raw[(row_idx - 1) * stride + i] };
+            let upper_left = if row_idx == 0 || i < bpp {
+                0
+            } else {
+                raw[(row_idx - 1) * stride + i - bpp]
+            };
+            let pred = match tag {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((left as u16 + up as u16) / 2) as u8,
+                4 => paeth_predictor(left, up, upper_left),
+                _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "unknown filter tag")),
+            };
+            raw[row_idx * stride + i] = src[i].wrapping_add(pred);
+        }
+    }
+    Ok(raw)
+}
+
+/// Return the channel (0..4) with the widest value range in `colors`, and that
+/// range. Used by median-cut to decide where to split a color box.
+fn widest_channel(colors: &[[u8; 4]]) -> (usize, i32) {
+    let mut best_ch = 0usize;
+    let mut best_range = -1i32;
+    for ch in 0..4 {
+        let mut lo = u8::MAX;
+        let mut hi = u8::MIN;
+        for c in colors {
+            lo = lo.min(c[ch]);
+            hi = hi.max(c[ch]);
+        }
+        let range = hi as i32 - lo as i32;
+        if range > best_range {
+            best_range = range;
+            best_ch = ch;
+        }
+    }
+    (best_ch, best_range)
+}
+
+/// The per-channel average color of a box, rounded to the nearest integer.
+fn average_color(colors: &[[u8; 4]]) -> [u8; 4] {
+    let mut sums = [0u64; 4];
+    for c in colors {
+        for ch in 0..4 {
+            sums[ch] += c[ch] as u64;
+        }
+    }
+    let n = colors.len() as u64;
+    let mut out = [0u8; 4];
+    for ch in 0..4 {
+        out[ch] = ((sums[ch] + n / 2) / n) as u8;
+    }
+    out
+}
+
+/// Build a palette of at most [`MAX_PALETTE`] representative colors by
+/// recursively splitting the color box with the largest axis range along its
+/// longest channel, taking each final box's average as a palette entry.
+fn median_cut(pixels: &[[u8; 4]], max_colors: usize) -> Vec<[u8; 4]> {
+    if pixels.is_empty() {
+        return Vec::new();
+    }
+    let mut boxes: Vec<Vec<[u8; 4]>> = vec![pixels.to_vec()];
+    while boxes.len() < max_colors {
+        let mut target = None;
+        let mut best_range = 0i32;
+        for (idx, b) in boxes.iter().enumerate() {
+            if b.len() < 2 {
+                continue;
+            }
+            let (_, range) = widest_channel(b);
+            if range > best_range {
+                best_range = range;
+                target = Some(idx);
+            }
+        }
+        let Some(idx) = target else { break };
+
+        let mut b = boxes.swap_remove(idx);
+        let (ch, _) = widest_channel(&b);
+        b.sort_by_key(|c| c[ch]);
+        let hi = b.split_off(b.len() / 2);
+        boxes.push(b);
+        boxes.push(hi);
+    }
+    boxes.iter().map(|b| average_color(b)).collect()
+}
+
+/// Index of the palette entry closest to `color` by squared RGBA distance.
+fn nearest_palette_index(palette: &[[u8; 4]], color: [u8; 4]) -> usize {
+    let mut best = 0usize;
+    let mut best_dist = i64::MAX;
+    for (idx, entry) in palette.iter().enumerate() {
+        let mut dist = 0i64;
+        for ch in 0..4 {
+            let d = color[ch] as i64 - entry[ch] as i64;
+            dist += d * d;
+        }
+        if dist < best_dist {
+            best_dist = dist;
+            best = idx;
+        }
+    }
+    best
+}
+
 #[derive(Default)]
 struct ShivanoshApp {
     input_paths: Vec<PathBuf>,
     shivanosh_paths: Vec<PathBuf>,
     status: String,
-    image_to_view: Option<RgbaImage>,
+    /// When set, new `.shivanosh` files are written with median-cut palette
+    /// indexing instead of truecolor frames.
+    use_palette: bool,
+    /// Thumbnails for every selected input and opened `.shivanosh` file.
+    gallery: Vec<GalleryItem>,
+    frames: Vec<ShivanoshFrame>,
+    current_frame: usize,
+    /// Wall-clock time (in egui's `input.time` seconds) the current frame began
+    /// displaying; `None` until the first repaint after a load.
+    frame_started: Option<f64>,
     texture_handle: Option<eframe::egui::TextureHandle>,
 }
 
@@ -23,7 +454,7 @@ impl ShivanoshApp {
 
         for input_path in &self.input_paths {
             let output_path = self.get_output_path(input_path);
-            match Self::convert_image_to_shivanosh(input_path, &output_path) {
+            match Self::convert_image_to_shivanosh(input_path, &output_path, self.use_palette) {
                 Ok(_) => {
                     self.status = format!("Successfully converted: {}", input_path.display());
                 }
@@ -41,31 +472,95 @@ impl ShivanoshApp {
         output_path
     }
 
-    fn convert_image_to_shivanosh(input_path: &Path, output_path: &Path) -> io::Result<()> {
-        let img = image::open(input_path).map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+    fn convert_image_to_shivanosh(input_path: &Path, output_path: &Path, palette: bool) -> io::Result<()> {
+        let frames = Self::load_source_frames(input_path)?;
+
+        if palette {
+            return Self::write_palette_shivanosh(&frames[0].image, output_path);
+        }
+
+        let (width, height) = frames[0].image.dimensions();
+        let color_type = detect_color_type(&frames);
 
-        let (width, height) = img.dimensions();
-        let mut pixel_data = Vec::new();
+        let mut file = File::create(output_path)?;
+        file.write_all(MAGIC_ANIM_CT)?;
+        file.write_all(&width.to_le_bytes())?;
+        file.write_all(&height.to_le_bytes())?;
+        file.write_all(&(frames.len() as u32).to_le_bytes())?;
+        file.write_all(&[color_type.to_byte()])?;
 
-        for (_, _, pixel) in img.pixels() {
-            pixel_data.extend_from_slice(&pixel.0); // Include alpha channel
+        for frame in &frames {
+            let block = encode_frame_block(&frame.image, color_type)?;
+            file.write_all(&frame.delay_ms.to_le_bytes())?;
+            file.write_all(&(block.len() as u32).to_le_bytes())?;
+            file.write_all(&block)?;
         }
+        Ok(())
+    }
+
+    /// Quantize `image` with median-cut and write it as a palette variant: the
+    /// palette (count + RGBA entries) followed by zlib-compressed 1-byte indices.
+    fn write_palette_shivanosh(image: &RgbaImage, output_path: &Path) -> io::Result<()> {
+        let (width, height) = image.dimensions();
+        let pixels: Vec<[u8; 4]> = image.pixels().map(|p| p.0).collect();
+        let palette = median_cut(&pixels, MAX_PALETTE);
+
+        let indices: Vec<u8> = pixels
+            .iter()
+            .map(|&c| nearest_palette_index(&palette, c) as u8)
+            .collect();
 
         let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
-        encoder.write_all(&pixel_data)?;
-        let compressed_data = encoder.finish()?;
+        encoder.write_all(&indices)?;
+        let compressed = encoder.finish()?;
 
         let mut file = File::create(output_path)?;
-        file.write_all(b"MYIF")?;
+        file.write_all(MAGIC_PALETTE)?;
         file.write_all(&width.to_le_bytes())?;
         file.write_all(&height.to_le_bytes())?;
-        file.write_all(&compressed_data)?;
+        file.write_all(&(palette.len() as u16).to_le_bytes())?;
+        for entry in &palette {
+            file.write_all(entry)?;
+        }
+        file.write_all(&compressed)?;
         Ok(())
     }
 
+    /// Decode the source image into one or more frames. Animated GIFs are read
+    /// through the `image` crate's frame API with their per-frame delays; every
+    /// other format yields a single frame with a zero delay.
+    fn load_source_frames(input_path: &Path) -> io::Result<Vec<ShivanoshFrame>> {
+        let is_gif = input_path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.eq_ignore_ascii_case("gif"))
+            .unwrap_or(false);
+
+        if is_gif {
+            let decoder = GifDecoder::new(BufReader::new(File::open(input_path)?))
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+            let mut frames = Vec::new();
+            for frame in decoder.into_frames() {
+                let frame = frame.map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+                let (numer, denom) = frame.delay().numer_denom_ms();
+                let delay_ms = if denom == 0 { 0 } else { (numer / denom).min(u16::MAX as u32) as u16 };
+                frames.push(ShivanoshFrame { image: frame.into_buffer(), delay_ms });
+            }
+            if frames.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "GIF contained no frames"));
+            }
+            Ok(frames)
+        } else {
+            let img = image::open(input_path)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+            Ok(vec![ShivanoshFrame { image: img.to_rgba8(), delay_ms: 0 }])
+        }
+    }
+
     fn open_file_dialog(&mut self) {
         if let Some(paths) = FileDialog::new().pick_files() {
             self.input_paths = paths;
+            self.rebuild_gallery();
         }
     }
 
@@ -73,14 +568,92 @@ impl ShivanoshApp {
         if let Some(paths) = FileDialog::new().add_filter("Shivanosh", &["shivanosh"]).pick_files() {
             self.shivanosh_paths = paths;
             self.view_shivanosh_images();
+            self.rebuild_gallery();
+        }
+    }
+
+    /// True for extensions the `image` crate can decode as an input.
+    fn is_supported_input_ext(ext: &str) -> bool {
+        matches!(
+            ext.to_ascii_lowercase().as_str(),
+            "png" | "jpg" | "jpeg" | "gif" | "bmp" | "webp" | "tif" | "tiff" | "ico" | "tga" | "pnm"
+        )
+    }
+
+    /// Route dropped paths into the input or `.shivanosh` lists by extension and
+    /// rebuild the gallery. Unrecognised extensions still produce a status row.
+    fn add_dropped_files(&mut self, paths: Vec<PathBuf>) {
+        for path in paths {
+            let ext = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or("")
+                .to_ascii_lowercase();
+            if ext == "shivanosh" {
+                if !self.shivanosh_paths.contains(&path) {
+                    self.shivanosh_paths.push(path);
+                }
+            } else if !self.input_paths.contains(&path) {
+                self.input_paths.push(path);
+            }
         }
+        self.rebuild_gallery();
+    }
+
+    /// Decode a thumbnail for every selected input and opened `.shivanosh` file.
+    /// Each decode is wrapped so a corrupt or unsupported file yields a status
+    /// row rather than aborting the batch or panicking.
+    fn rebuild_gallery(&mut self) {
+        let mut gallery = Vec::new();
+        for path in self.shivanosh_paths.iter().chain(self.input_paths.iter()) {
+            let (thumbnail, status) = match Self::load_thumbnail(path) {
+                Ok(thumb) => (Some(thumb), "ok".to_string()),
+                Err(e) => (None, e),
+            };
+            gallery.push(GalleryItem {
+                path: path.clone(),
+                thumbnail,
+                status,
+                texture: None,
+            });
+        }
+        self.gallery = gallery;
+    }
+
+    /// Decode a path into a small preview image, classifying by extension first
+    /// and returning a human-readable error string on any failure.
+    fn load_thumbnail(path: &Path) -> Result<RgbaImage, String> {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+
+        let full = if ext == "shivanosh" {
+            Self::decompress_shivanosh(path).map_err(|e| e.to_string())?
+        } else if Self::is_supported_input_ext(&ext) {
+            Self::load_source_frames(path)
+                .map_err(|e| e.to_string())?
+                .remove(0)
+                .image
+        } else {
+            return Err(format!("skipped (unsupported .{ext})"));
+        };
+
+        Ok(image::imageops::thumbnail(
+            &full,
+            full.width().min(THUMB_MAX).max(1),
+            full.height().min(THUMB_MAX).max(1),
+        ))
     }
 
     fn view_shivanosh_images(&mut self) {
         if let Some(path) = self.shivanosh_paths.first() {
-            match Self::decompress_shivanosh(path) {
-                Ok(img) => {
-                    self.image_to_view = Some(img);
+            match Self::decompress_shivanosh_frames(path) {
+                Ok(frames) => {
+                    self.frames = frames;
+                    self.current_frame = 0;
+                    self.frame_started = None;
                     self.texture_handle = None; // Reset texture handle
                 }
                 Err(e) => self.status = format!("Error viewing {}: {e}", path.display()),
@@ -88,13 +661,25 @@ impl ShivanoshApp {
         }
     }
 
+    /// Decode the first frame of a `.shivanosh` file. Used by the gallery to
+    /// build a thumbnail from a single `RgbaImage`.
     fn decompress_shivanosh(path: &Path) -> io::Result<RgbaImage> {
+        let mut frames = Self::decompress_shivanosh_frames(path)?;
+        if frames.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "file contained no frames",
+            ));
+        }
+        Ok(frames.remove(0).image)
+    }
+
+    /// Decode every frame of a `.shivanosh` file, handling the animated format
+    /// as well as the single-frame and legacy magics for backward compatibility.
+    fn decompress_shivanosh_frames(path: &Path) -> io::Result<Vec<ShivanoshFrame>> {
         let mut file = File::open(path)?;
         let mut magic = [0u8; 4];
         file.read_exact(&mut magic)?;
-        if &magic != b"MYIF" {
-            return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid .shivanosh file"));
-        }
 
         let mut width_bytes = [0u8; 4];
         file.read_exact(&mut width_bytes)?;
@@ -104,47 +689,172 @@ impl ShivanoshApp {
         file.read_exact(&mut height_bytes)?;
         let height = u32::from_le_bytes(height_bytes);
 
-        let mut compressed_data = Vec::new();
-        file.read_to_end(&mut compressed_data)?;
+        if &magic == MAGIC_ANIM || &magic == MAGIC_ANIM_CT {
+            let mut count_bytes = [0u8; 4];
+            file.read_exact(&mut count_bytes)?;
+            let count = u32::from_le_bytes(count_bytes);
+            if count == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "animated file declares zero frames",
+                ));
+            }
+
+            // The channel-typed variant carries a color type byte; the older
+            // plain animated variant is always full RGBA.
+            let color_type = if &magic == MAGIC_ANIM_CT {
+                let mut ct = [0u8; 1];
+                file.read_exact(&mut ct)?;
+                ColorType::from_byte(ct[0])?
+            } else {
+                ColorType::Rgba
+            };
+
+            let mut frames = Vec::with_capacity(count as usize);
+            for _ in 0..count {
+                let mut delay_bytes = [0u8; 2];
+                file.read_exact(&mut delay_bytes)?;
+                let delay_ms = u16::from_le_bytes(delay_bytes);
+
+                let mut len_bytes = [0u8; 4];
+                file.read_exact(&mut len_bytes)?;
+                let len = u32::from_le_bytes(len_bytes) as usize;
+
+                let mut block = vec![0u8; len];
+                file.read_exact(&mut block)?;
+                let image = decode_frame_block(&block, width, height, color_type, true)?;
+                frames.push(ShivanoshFrame { image, delay_ms });
+            }
+            return Ok(frames);
+        }
+
+        if &magic == MAGIC_PALETTE {
+            let mut count_bytes = [0u8; 2];
+            file.read_exact(&mut count_bytes)?;
+            let count = u16::from_le_bytes(count_bytes) as usize;
+
+            let mut palette = vec![[0u8; 4]; count];
+            for entry in &mut palette {
+                file.read_exact(entry)?;
+            }
+
+            let mut compressed = Vec::new();
+            file.read_to_end(&mut compressed)?;
+            let mut decoder = flate2::read::ZlibDecoder::new(&compressed[..]);
+            let mut indices = Vec::new();
+            decoder.read_to_end(&mut indices)?;
 
-        let mut decoder = flate2::read::ZlibDecoder::new(&compressed_data[..]);
-        let mut decompressed_data = Vec::new();
-        decoder.read_to_end(&mut decompressed_data)?;
+            if width == 0 || height == 0 {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "zero image dimension"));
+            }
+            let expected = width as usize * height as usize;
+            if indices.len() != expected {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "index count mismatch"));
+            }
 
-        let mut img = RgbaImage::new(width, height);
-        let pixel_count = (width * height * 4) as usize;
-        for i in 0..pixel_count / 4 {
-            let r = decompressed_data[i * 4];
-            let g = decompressed_data[i * 4 + 1];
-            let b = decompressed_data[i * 4 + 2];
-            let a = decompressed_data[i * 4 + 3];
-            img.put_pixel(
-                (i as u32 % width) as u32,
-                (i as u32 / width) as u32,
-                image::Rgba([r, g, b, a]),
-            );
+            let mut image = RgbaImage::new(width, height);
+            for (i, &idx) in indices.iter().enumerate() {
+                let color = *palette.get(idx as usize).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, "palette index out of range")
+                })?;
+                image.put_pixel(i as u32 % width, i as u32 / width, image::Rgba(color));
+            }
+            return Ok(vec![ShivanoshFrame { image, delay_ms: 0 }]);
         }
 
-        Ok(img)
+        // Single-frame formats: the remainder of the file is one zlib block.
+        let filtered = match &magic {
+            m if m == MAGIC_FILTERED => true,
+            m if m == MAGIC_LEGACY => false,
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid .shivanosh file")),
+        };
+        let mut block = Vec::new();
+        file.read_to_end(&mut block)?;
+        let image = decode_frame_block(&block, width, height, ColorType::Rgba, filtered)?;
+        Ok(vec![ShivanoshFrame { image, delay_ms: 0 }])
+    }
+
+    fn export_current_image(&mut self) {
+        if self.frames.is_empty() {
+            self.status = "No .shivanosh image loaded to export.".to_string();
+            return;
+        }
+        let image = self.frames[self.current_frame].image.clone();
+
+        let mut dialog = FileDialog::new();
+        for fmt in ExportFormat::ALL {
+            dialog = dialog.add_filter(fmt.label(), &[fmt.extension()]);
+        }
+
+        if let Some(path) = dialog.save_file() {
+            match Self::convert_shivanosh_to_image(&image, &path) {
+                Ok(_) => self.status = format!("Exported to {}", path.display()),
+                Err(e) => self.status = format!("Export failed: {e}"),
+            }
+        }
+    }
+
+    /// Write a decoded `.shivanosh` image back out to a standard format, chosen
+    /// from the destination path's extension.
+    fn convert_shivanosh_to_image(image: &RgbaImage, output_path: &Path) -> io::Result<()> {
+        let ext = output_path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        match ext.as_deref() {
+            Some("png") => image
+                .save_with_format(output_path, image::ImageFormat::Png)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string())),
+            // The JPEG encoder rejects `Rgba8`, so flatten to RGB before writing.
+            Some("jpg") | Some("jpeg") => {
+                image::DynamicImage::ImageRgba8(image.clone())
+                    .to_rgb8()
+                    .save_with_format(output_path, image::ImageFormat::Jpeg)
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))
+            }
+            Some("bmp") => image
+                .save_with_format(output_path, image::ImageFormat::Bmp)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string())),
+            Some("tif") | Some("tiff") => image
+                .save_with_format(output_path, image::ImageFormat::Tiff)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string())),
+            // WebP is an enumerated target but the pinned `image` crate has no
+            // WebP encoder, so surface the explicit unsupported-target error.
+            Some("webp") => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "unsupported export target: webp",
+            )),
+            other => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unsupported export format: {}", other.unwrap_or("(none)")),
+            )),
+        }
     }
 }
 
 impl eframe::App for ShivanoshApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // Drag-and-drop intake: classify dropped files and refresh thumbnails.
+        let dropped: Vec<PathBuf> = ctx.input(|i| {
+            i.raw
+                .dropped_files
+                .iter()
+                .filter_map(|f| f.path.clone())
+                .collect()
+        });
+        if !dropped.is_empty() {
+            self.add_dropped_files(dropped);
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
-            ui.label("Selected Files:");
-            if self.input_paths.is_empty() {
-                ui.label("No files selected.");
-            } else {
-                for path in &self.input_paths {
-                    ui.label(path.display().to_string());
-                }
-            }
+            ui.label("Drag images or .shivanosh files here, or:");
 
             if ui.button("Select Images").clicked() {
                 self.open_file_dialog();
             }
 
+            ui.checkbox(&mut self.use_palette, "Palette (indexed color)");
+
             if ui.button("Convert to .shivanosh").clicked() {
                 self.convert_to_shivanosh();
             }
@@ -152,7 +862,84 @@ impl eframe::App for ShivanoshApp {
             ui.separator();
             ui.label(format!("Status: {}", self.status));
 
-            if let Some(image) = &self.image_to_view {
+            if !self.gallery.is_empty() {
+                ui.separator();
+                ui.label("Gallery:");
+                let thumb = egui::vec2(THUMB_MAX as f32, THUMB_MAX as f32);
+                egui::ScrollArea::vertical().max_height(260.0).show(ui, |ui| {
+                    ui.horizontal_wrapped(|ui| {
+                        for item in &mut self.gallery {
+                            ui.allocate_ui(egui::vec2(thumb.x, thumb.y + 36.0), |ui| {
+                                ui.vertical(|ui| {
+                                    let (rect, _) =
+                                        ui.allocate_exact_size(thumb, egui::Sense::hover());
+                                    if ui.is_rect_visible(rect) {
+                                        // Lazily build the texture only while visible.
+                                        if item.texture.is_none() {
+                                            if let Some(img) = &item.thumbnail {
+                                                let size = [img.width() as usize, img.height() as usize];
+                                                let pixels: Vec<egui::Color32> = img
+                                                    .pixels()
+                                                    .map(|p| {
+                                                        let [r, g, b, a] = p.0;
+                                                        egui::Color32::from_rgba_premultiplied(r, g, b, a)
+                                                    })
+                                                    .collect();
+                                                let color_image = egui::ColorImage { size, pixels };
+                                                item.texture = Some(ui.ctx().load_texture(
+                                                    item.path.to_string_lossy(),
+                                                    color_image,
+                                                    egui::TextureOptions::LINEAR,
+                                                ));
+                                            }
+                                        }
+                                        if let Some(tex) = &item.texture {
+                                            egui::Image::new(tex).paint_at(ui, rect);
+                                        } else {
+                                            ui.painter().rect_filled(
+                                                rect,
+                                                2.0,
+                                                egui::Color32::DARK_GRAY,
+                                            );
+                                        }
+                                    } else {
+                                        // Evict off-screen textures to bound GPU memory.
+                                        item.texture = None;
+                                    }
+
+                                    let name = item
+                                        .path
+                                        .file_name()
+                                        .map(|n| n.to_string_lossy().into_owned())
+                                        .unwrap_or_default();
+                                    ui.small(name);
+                                    ui.small(item.status.as_str());
+                                });
+                            });
+                        }
+                    });
+                });
+            }
+
+            if !self.frames.is_empty() {
+                // Advance the animation, looping, with each frame shown for its
+                // stored delay. Single-frame files simply sit on frame 0.
+                if self.frames.len() > 1 {
+                    let now = ctx.input(|i| i.time);
+                    let started = *self.frame_started.get_or_insert(now);
+                    let delay_ms = self.frames[self.current_frame].delay_ms.max(10) as f64;
+                    let elapsed_ms = (now - started) * 1000.0;
+                    if elapsed_ms >= delay_ms {
+                        self.current_frame = (self.current_frame + 1) % self.frames.len();
+                        self.frame_started = Some(now);
+                        self.texture_handle = None;
+                        ctx.request_repaint();
+                    } else {
+                        ctx.request_repaint_after(Duration::from_millis((delay_ms - elapsed_ms) as u64));
+                    }
+                }
+
+                let image = &self.frames[self.current_frame].image;
                 if self.texture_handle.is_none() {
                     let size = [image.width() as usize, image.height() as usize];
                     let pixels: Vec<eframe::egui::Color32> = image.pixels()
@@ -168,16 +955,17 @@ impl eframe::App for ShivanoshApp {
                 }
 
                 if let Some(texture) = &self.texture_handle {
-                    let available_size = ui.available_size();
-                    let texture_size = texture.size_vec2();
-                    let scale = (available_size.x / texture_size.x).min(available_size.y / texture_size.y);
-                    ui.add(egui::Image::new(texture));  // Removed scaling
+                    ui.add(egui::Image::new(texture));
                 }
             }
 
             if ui.button("View .shivanosh Images").clicked() {
                 self.open_shivanosh_dialog();
             }
+
+            if !self.frames.is_empty() && ui.button("Export").clicked() {
+                self.export_current_image();
+            }
         });
     }
 }